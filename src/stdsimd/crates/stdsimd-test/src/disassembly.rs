@@ -43,27 +43,66 @@ pub(crate) fn disassemble_myself() -> HashMap<String, Vec<Function>> {
 
         parse_otool(str::from_utf8(&output.stdout).expect("stdout not utf8"))
     } else {
-        let objdump =
-            env::var("OBJDUMP").unwrap_or_else(|_| "objdump".to_string());
-        let output = Command::new(objdump.clone())
-            .arg("--disassemble")
-            .arg(&me)
-            .output()
-            .expect(&format!(
-                "failed to execute objdump. OBJDUMP={}",
-                objdump
-            ));
-        println!(
-            "{}\n{}",
-            output.status,
-            String::from_utf8_lossy(&output.stderr)
-        );
-        assert!(output.status.success());
+        let candidates = objdump_candidates(env::var("OBJDUMP").ok());
+        let output = run_disassembler(&candidates, |cmd| {
+            let output = Command::new(&cmd[0])
+                .args(&cmd[1..])
+                .arg(&me)
+                .output()
+                .map_err(|e| e.to_string())?;
+            println!(
+                "{}\n{}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr)
+            );
+            if !output.status.success() {
+                return Err(format!("exited with {}", output.status));
+            }
+            String::from_utf8(output.stdout)
+                .map_err(|_| "stdout not utf8".to_string())
+        });
 
-        parse_objdump(str::from_utf8(&output.stdout).expect("stdout not utf8"))
+        parse_objdump(&output)
     }
 }
 
+/// The disassembler commands to try in order, without the path of the binary.
+///
+/// `OBJDUMP` replaces the default `objdump` and `llvm-objdump` with a single
+/// command. If it is just a program, `--disassemble` is passed to it as
+/// before; otherwise its arguments are used as given.
+fn objdump_candidates(objdump: Option<String>) -> Vec<Vec<String>> {
+    let custom = objdump
+        .map(|cmd| {
+            cmd.split_whitespace().map(String::from).collect::<Vec<_>>()
+        })
+        .filter(|cmd| !cmd.is_empty());
+    match custom {
+        Some(ref cmd) if cmd.len() > 1 => vec![cmd.clone()],
+        Some(cmd) => vec![vec![cmd[0].clone(), "--disassemble".to_string()]],
+        None => ["objdump", "llvm-objdump"]
+            .iter()
+            .map(|tool| vec![tool.to_string(), "--disassemble".to_string()])
+            .collect(),
+    }
+}
+
+/// Returns the output of the first command for which `run` succeeds,
+/// panicking with the reason each one failed if none does.
+fn run_disassembler<F>(candidates: &[Vec<String>], mut run: F) -> String
+where
+    F: FnMut(&[String]) -> Result<String, String>,
+{
+    let mut failures = Vec::new();
+    for cmd in candidates {
+        match run(cmd) {
+            Ok(output) => return output,
+            Err(e) => failures.push(format!("`{}`: {}", cmd.join(" "), e)),
+        }
+    }
+    panic!("failed to disassemble, tried:\n{}", failures.join("\n"));
+}
+
 fn parse_objdump(output: &str) -> HashMap<String, Vec<Function>> {
     let mut lines = output.lines();
     let expected_len =
@@ -212,3 +251,92 @@ fn parse_dumpbin(output: &str) -> HashMap<String, Vec<Function>> {
 
     ret
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{objdump_candidates, parse_objdump, run_disassembler};
+    use strip_trailing;
+
+    #[test]
+    #[cfg(all(target_arch = "x86_64", not(target_os = "macos")))]
+    fn padding_does_not_count() {
+        let mut output = String::from(
+            "0000000000001000 <_ZN3foo3bar17h0123456789abcdefE>:\n\
+             \x20   1000:\t0f 58 c1             \taddps  %xmm1,%xmm0\n\
+             \x20   1003:\tc3                   \tretq   \n",
+        );
+        for i in 0..24 {
+            output.push_str(&format!(
+                "    {:x}:\tcc                   \tint3   \n",
+                0x1004 + i
+            ));
+        }
+        output.push_str(
+            "    101c:\t66 2e 0f 1f 84 00 00 \tnopw   %cs:0x0(%rax,%rax,1)\n\
+             \x20   1023:\t00 00 00 \n\
+             \n",
+        );
+
+        let functions = parse_objdump(&output);
+        let instrs = &functions["foo::bar"][0].instrs;
+        assert_eq!(instrs.len(), 28);
+        assert_eq!(instrs[0].parts, ["addps", "%xmm1,%xmm0"]);
+
+        let stripped = strip_trailing(instrs);
+        assert_eq!(stripped.len(), 1);
+        assert_eq!(stripped[0].parts[0], "addps");
+    }
+
+    fn cmd(parts: &[&str]) -> Vec<String> {
+        parts.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn disassembler_candidates() {
+        assert_eq!(
+            objdump_candidates(None),
+            [
+                cmd(&["objdump", "--disassemble"]),
+                cmd(&["llvm-objdump", "--disassemble"])
+            ]
+        );
+        assert_eq!(
+            objdump_candidates(Some("  ".to_string())),
+            objdump_candidates(None)
+        );
+        assert_eq!(
+            objdump_candidates(Some("llvm-objdump-7".to_string())),
+            [cmd(&["llvm-objdump-7", "--disassemble"])]
+        );
+        assert_eq!(
+            objdump_candidates(Some(
+                "objdump -d --no-show-raw-insn".to_string()
+            )),
+            [cmd(&["objdump", "-d", "--no-show-raw-insn"])]
+        );
+    }
+
+    #[test]
+    fn falls_back_to_next_disassembler() {
+        let candidates = [cmd(&["no-such-objdump"]), cmd(&["objdump"])];
+        let mut tried = Vec::new();
+        let output = run_disassembler(&candidates, |cmd| {
+            tried.push(cmd[0].clone());
+            if cmd[0] == "objdump" {
+                Ok("disassembly".to_string())
+            } else {
+                Err("not found".to_string())
+            }
+        });
+        assert_eq!(output, "disassembly");
+        assert_eq!(tried, ["no-such-objdump", "objdump"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "`b --disassemble`: not found")]
+    fn panics_after_all_disassemblers_fail() {
+        let candidates =
+            [cmd(&["a", "--disassemble"]), cmd(&["b", "--disassemble"])];
+        run_disassembler(&candidates, |_| Err("not found".to_string()));
+    }
+}
@@ -50,6 +50,9 @@ struct Instruction {
 }
 
 fn normalize(symbol: &str) -> String {
+    if let Some(path) = demangle_v0(symbol) {
+        return path;
+    }
     let symbol = rustc_demangle::demangle(symbol).to_string();
     match symbol.rfind("::h") {
         Some(i) => symbol[..i].to_string(),
@@ -57,20 +60,85 @@ fn normalize(symbol: &str) -> String {
     }
 }
 
+/// Demangles a symbol in the v0 mangling scheme (`_R...`), which the
+/// version of `rustc_demangle` we use doesn't know about yet.
+///
+/// The crate disambiguators and the instantiating crate are left out so the
+/// result matches a legacy symbol with its hash stripped. Only plain paths of
+/// modules and items are supported, anything else returns `None`.
+fn demangle_v0(symbol: &str) -> Option<String> {
+    // macOS prefixes all symbols with an extra underscore.
+    let symbol = if symbol.starts_with("__R") {
+        &symbol[1..]
+    } else {
+        symbol
+    };
+    if !symbol.starts_with("_R") {
+        return None;
+    }
+    let mut rest = &symbol.as_bytes()[2..];
+    // Whatever follows the path is the instantiating crate, which we ignore.
+    v0_path(&mut rest).map(|path| path.join("::"))
+}
+
+fn v0_path(rest: &mut &[u8]) -> Option<Vec<String>> {
+    let (&tag, tail) = rest.split_first()?;
+    *rest = tail;
+    match tag {
+        b'C' => Some(vec![v0_ident(rest)?]),
+        b'N' => {
+            // Upper case namespaces are closures and shims, which don't have
+            // a name of their own.
+            let (&namespace, tail) = rest.split_first()?;
+            if !namespace.is_ascii_lowercase() {
+                return None;
+            }
+            *rest = tail;
+            let mut path = v0_path(rest)?;
+            path.push(v0_ident(rest)?);
+            Some(path)
+        }
+        _ => None,
+    }
+}
+
+fn v0_ident(rest: &mut &[u8]) -> Option<String> {
+    // Skip the disambiguator, a base-62 number terminated by `_`.
+    if rest.first() == Some(&b's') {
+        let end = rest.iter().position(|&b| b == b'_')?;
+        *rest = &rest[end + 1..];
+    }
+    let digits = rest.iter().take_while(|b| b.is_ascii_digit()).count();
+    if digits == 0 {
+        // Punycode identifiers start with `u`.
+        return None;
+    }
+    let len: usize = str::from_utf8(&rest[..digits]).ok()?.parse().ok()?;
+    *rest = &rest[digits..];
+    // Identifiers starting with a digit or `_` are separated by a `_`.
+    if rest.first() == Some(&b'_') {
+        *rest = &rest[1..];
+    }
+    if rest.len() < len {
+        return None;
+    }
+    let ident = str::from_utf8(&rest[..len]).ok()?.to_string();
+    *rest = &rest[len..];
+    Some(ident)
+}
+
 /// Main entry point for this crate, called by the `#[assert_instr]` macro.
 ///
 /// This asserts that the function at `fnptr` contains the instruction
-/// `expected` provided.
+/// `expected` provided. If `STDSIMD_ASSERT_JSON` is set, failures are also
+/// printed as a line of JSON.
 pub fn assert(fnptr: usize, fnname: &str, expected: &str) {
     let mut fnname = fnname.to_string();
     let functions = get_functions(fnptr, &mut fnname);
     assert_eq!(functions.len(), 1);
     let function = &functions[0];
 
-    let mut instrs = &function.instrs[..];
-    while instrs.last().map_or(false, |s| s.parts == ["nop"]) {
-        instrs = &instrs[..instrs.len() - 1];
-    }
+    let instrs = trim_instrs(&function.instrs);
 
     // Look for `expected` as the first part of any instruction in this
     // function, returning if we do indeed find it.
@@ -141,7 +209,9 @@ pub fn assert(fnptr: usize, fnname: &str, expected: &str) {
             // adding here a long list of exceptions.
             _ => 22,
         });
-    let probably_only_one_instruction = instrs.len() < instruction_limit;
+    // Don't count the instructions that end or pad the function.
+    let instrs_len = strip_trailing(instrs).len();
+    let probably_only_one_instruction = instrs_len < instruction_limit;
 
     if found && probably_only_one_instruction && !inlining_failed {
         return;
@@ -149,14 +219,9 @@ pub fn assert(fnptr: usize, fnname: &str, expected: &str) {
 
     // Help debug by printing out the found disassembly, and then panic as we
     // didn't find the instruction.
-    println!("disassembly for {}: ", fnname,);
-    for (i, instr) in instrs.iter().enumerate() {
-        let mut s = format!("\t{:2}: ", i);
-        for part in &instr.parts {
-            s.push_str(part);
-            s.push_str(" ");
-        }
-        println!("{}", s);
+    print_disassembly(&fnname, instrs);
+    if env::var_os("STDSIMD_ASSERT_JSON").is_some() {
+        println!("{}", json_report(&fnname, expected, found, instrs_len, instrs));
     }
 
     if !found {
@@ -168,7 +233,7 @@ pub fn assert(fnptr: usize, fnname: &str, expected: &str) {
         panic!(
             "instruction found, but the disassembly contains too many \
              instructions: #instructions = {} >= {} (limit)",
-            instrs.len(),
+            instrs_len,
             instruction_limit
         );
     } else if inlining_failed {
@@ -179,6 +244,160 @@ pub fn assert(fnptr: usize, fnname: &str, expected: &str) {
     }
 }
 
+/// Asserts that the function at `fnptr` contains exactly `count`
+/// instructions starting with `expected`.
+///
+/// This is useful for intrinsics that should expand to a known number of
+/// copies of an instruction, e.g. unrolled stores. See also the
+/// `assert_instr_count!` macro.
+pub fn assert_instr_count(
+    fnptr: usize, fnname: &str, expected: &str, count: usize,
+) {
+    let mut fnname = fnname.to_string();
+    let functions = get_functions(fnptr, &mut fnname);
+    assert_eq!(functions.len(), 1);
+    let instrs = trim_instrs(&functions[0].instrs);
+
+    let found = count_instrs(instrs, expected);
+    if found == count {
+        return;
+    }
+
+    print_disassembly(&fnname, instrs);
+    panic!(
+        "expected {} `{}` instructions in the disassembly, found {}",
+        count, expected, found
+    );
+}
+
+/// Asserts that a function contains exactly `count` instructions starting
+/// with `expected`, see `assert_instr_count`.
+#[macro_export]
+macro_rules! assert_instr_count {
+    ($f:path, $expected:expr, $count:expr) => {
+        $crate::assert_instr_count(
+            $f as usize,
+            stringify!($f),
+            $expected,
+            $count,
+        )
+    };
+}
+
+/// Counts the instructions whose mnemonic starts with `expected`.
+fn count_instrs(instrs: &[Instruction], expected: &str) -> usize {
+    instrs
+        .iter()
+        .filter(|instr| {
+            instr.parts.get(0).map_or(false, |part| part.starts_with(expected))
+        }).count()
+}
+
+/// Strips the trailing `nop`s that pad functions.
+fn trim_instrs(instrs: &[Instruction]) -> &[Instruction] {
+    let mut instrs = instrs;
+    while instrs.last().map_or(false, |s| s.parts == ["nop"]) {
+        instrs = &instrs[..instrs.len() - 1];
+    }
+    instrs
+}
+
+// Prefixes of the instructions that return from or pad the end of a
+// function on each target. They're not part of the intrinsic being tested,
+// so they don't count towards the instruction limit in `assert`.
+cfg_if! {
+    if #[cfg(any(target_arch = "x86", target_arch = "x86_64"))] {
+        // `data16` and `cs` prefix the long `nopw` padding.
+        const TRAILING_INSTRS: &[&str] =
+            &["ret", "nop", "int3", "ud2", "data16", "cs"];
+    } else if #[cfg(any(target_arch = "arm", target_arch = "aarch64"))] {
+        const TRAILING_INSTRS: &[&str] = &["ret", "bx", "nop", "udf"];
+    } else {
+        const TRAILING_INSTRS: &[&str] = &["nop"];
+    }
+}
+
+/// Strips the instructions in `TRAILING_INSTRS` from the end of a function,
+/// along with the empty lines objdump uses to continue long instructions.
+fn strip_trailing(instrs: &[Instruction]) -> &[Instruction] {
+    let mut instrs = instrs;
+    while instrs.last().map_or(false, |i| match i.parts.get(0) {
+        Some(part) => TRAILING_INSTRS.iter().any(|t| part.starts_with(t)),
+        None => true,
+    }) {
+        instrs = &instrs[..instrs.len() - 1];
+    }
+    instrs
+}
+
+fn print_disassembly(fnname: &str, instrs: &[Instruction]) {
+    println!("disassembly for {}: ", fnname,);
+    for (i, instr) in instrs.iter().enumerate() {
+        let mut s = format!("\t{:2}: ", i);
+        for part in &instr.parts {
+            s.push_str(part);
+            s.push_str(" ");
+        }
+        println!("{}", s);
+    }
+}
+
+/// Formats a failed `assert` as a single line of JSON, which is printed when
+/// `STDSIMD_ASSERT_JSON` is set so that CI can collect the failures.
+fn json_report(
+    fnname: &str, expected: &str, found: bool, instrs_len: usize,
+    instrs: &[Instruction],
+) -> String {
+    let disassembly = instrs
+        .iter()
+        .map(|instr| json_string(&instr.parts.join(" ")))
+        .collect::<Vec<_>>();
+    format!(
+        "{{\"function\":{},\"expected\":{},\"found\":{},\
+         \"instructions\":{},\"disassembly\":[{}]}}",
+        json_string(fnname),
+        json_string(expected),
+        found,
+        instrs_len,
+        disassembly.join(",")
+    )
+}
+
+fn json_string(s: &str) -> String {
+    let mut ret = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                ret.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+/// Returns the disassembly of the function named `fnname`, for assertions
+/// that `assert` can't express.
+///
+/// Each instruction is returned as the list of its parts, e.g. `["addps",
+/// "%xmm1,%xmm0"]`. The name is the demangled path of the function without
+/// its hash, and `None` is returned unless exactly one function has it.
+pub fn disassembly_of(fnname: &str) -> Option<Vec<Vec<String>>> {
+    match DISASSEMBLY.get(fnname) {
+        Some(functions) if functions.len() == 1 => Some(
+            functions[0]
+                .instrs
+                .iter()
+                .map(|instr| instr.parts.clone())
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
 fn get_functions(fnptr: usize, fnname: &mut String) -> &'static [Function] {
     // Translate this function pointer to a symbolic name that we'd have found
     // in the disassembly.
@@ -221,3 +440,90 @@ pub fn assert_skip_test_ok(name: &str) {
 
 // See comment in `assert-instr-macro` crate for why this exists
 pub static mut _DONT_DEDUP: &'static str = "";
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        count_instrs, demangle_v0, disassembly_of, json_report, normalize,
+        trim_instrs, Instruction,
+    };
+
+    #[test]
+    fn normalize_legacy() {
+        assert_eq!(
+            normalize("_ZN4core4arch3x863sse10_mm_add_ps17h0123456789abcdefE"),
+            "core::arch::x86::sse::_mm_add_ps"
+        );
+        assert_eq!(normalize("memcpy"), "memcpy");
+    }
+
+    #[test]
+    fn normalize_v0() {
+        let expected = "core::arch::x86::sse::_mm_add_ps";
+        let symbol = "_RNvNtNtNtCs1234_4core4arch3x863sse10__mm_add_ps";
+        assert_eq!(normalize(symbol), expected);
+        // With an instantiating crate, and with the macOS prefix.
+        let instantiated = format!("{}Cs5678_12stdsimd_test", symbol);
+        assert_eq!(normalize(&instantiated), expected);
+        assert_eq!(normalize(&format!("_{}", symbol)), expected);
+    }
+
+    #[test]
+    fn normalize_v0_unsupported() {
+        // A closure inside `core::foo`, left to `rustc_demangle`.
+        assert_eq!(demangle_v0("_RNCNvCs1234_4core3foo0"), None);
+    }
+
+    fn known_function(a: u32, b: u32) -> u32 {
+        a.wrapping_mul(b)
+    }
+
+    // Keeps `known_function` from being optimized away.
+    #[used]
+    static KNOWN_FUNCTION: fn(u32, u32) -> u32 = known_function;
+
+    #[test]
+    fn disassembly_of_known_function() {
+        let instrs =
+            disassembly_of("stdsimd_test::tests::known_function").unwrap();
+        assert!(!instrs.is_empty());
+        assert!(instrs.iter().all(|parts| !parts.is_empty()));
+        assert_eq!(disassembly_of("stdsimd_test::tests::missing"), None);
+    }
+
+    fn instrs(disassembly: &[&str]) -> Vec<Instruction> {
+        disassembly
+            .iter()
+            .map(|instr| Instruction {
+                parts: instr.split_whitespace().map(String::from).collect(),
+            }).collect()
+    }
+
+    #[test]
+    fn count_repeated_instruction() {
+        // Three unrolled stores, padded with `nop`s.
+        let instrs = instrs(&[
+            "movl $0x1,(%rdi)",
+            "movl $0x2,0x4(%rdi)",
+            "movl $0x3,0x8(%rdi)",
+            "retq",
+            "nop",
+            "nop",
+        ]);
+        let instrs = trim_instrs(&instrs);
+        assert_eq!(count_instrs(instrs, "mov"), 3);
+        assert_eq!(count_instrs(instrs, "ret"), 1);
+        assert_eq!(count_instrs(instrs, "nop"), 0);
+    }
+
+    #[test]
+    fn json_failure_report() {
+        let instrs = instrs(&["movaps %xmm0,(%rdi)", "retq"]);
+        assert_eq!(
+            json_report("foo::\"bar\"\t", "addps", false, 1, &instrs),
+            "{\"function\":\"foo::\\\"bar\\\"\\u0009\",\"expected\":\"addps\",\
+             \"found\":false,\"instructions\":1,\
+             \"disassembly\":[\"movaps %xmm0,(%rdi)\",\"retq\"]}"
+        );
+    }
+}
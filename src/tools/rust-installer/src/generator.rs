@@ -51,6 +51,9 @@ actor!{
 
         /// The location to put the final image and tarball
         output_dir: String = "./dist",
+
+        /// Glob patterns of paths in the image to leave out of the installer
+        exclude: Vec<String> = Vec::new(),
     }
 }
 
@@ -67,7 +70,7 @@ impl Generator {
         // Copy the image and write the manifest
         let component_dir = package_dir.join(&self.component_name);
         create_dir_all(&component_dir)?;
-        copy_and_manifest(self.image_dir.as_ref(), &component_dir, &self.bulk_dirs)?;
+        copy_and_manifest(self.image_dir.as_ref(), &component_dir, &self.bulk_dirs, &self.exclude)?;
 
         // Write the component name
         let components = package_dir.join("components");
@@ -108,13 +111,14 @@ impl Generator {
 }
 
 /// Copies the `src` directory recursively to `dst`, writing `manifest.in` too.
-fn copy_and_manifest(src: &Path, dst: &Path, bulk_dirs: &str) -> Result<()> {
+/// Paths matching the `exclude` patterns are neither copied nor listed.
+fn copy_and_manifest(src: &Path, dst: &Path, bulk_dirs: &str, exclude: &[String]) -> Result<()> {
     let manifest = create_new_file(dst.join("manifest.in"))?;
     let bulk_dirs: Vec<_> = bulk_dirs.split(',')
         .filter(|s| !s.is_empty())
         .map(Path::new).collect();
 
-    copy_with_callback(src, dst, |path, file_type| {
+    copy_with_callback(src, dst, exclude, |path, file_type| {
         // We need paths to be compatible with both Unix and Windows.
         if path.components().filter_map(|c| c.as_os_str().to_str()).any(|s| s.contains('\\')) {
             bail!("rust-installer doesn't support '\\' in path components: {:?}", path);
@@ -58,7 +58,7 @@ fn combine(matches: &ArgMatches) -> Result<()> {
 }
 
 fn generate(matches: &ArgMatches) -> Result<()> {
-    let generator = parse!(matches => installer::Generator {
+    let mut generator = parse!(matches => installer::Generator {
         "product-name" => product_name,
         "component-name" => component_name,
         "package-name" => package_name,
@@ -71,6 +71,9 @@ fn generate(matches: &ArgMatches) -> Result<()> {
         "work-dir" => work_dir,
         "output-dir" => output_dir,
     });
+    if let Some(patterns) = matches.value_of("exclude") {
+        generator.exclude(split_list(patterns));
+    }
 
     generator.run().chain_err(|| "failed to generate installer")
 }
@@ -88,11 +91,22 @@ fn script(matches: &ArgMatches) -> Result<()> {
 }
 
 fn tarball(matches: &ArgMatches) -> Result<()> {
-    let tarballer = parse!(matches => installer::Tarballer {
+    let mut tarballer = parse!(matches => installer::Tarballer {
         "input" => input,
         "output" => output,
         "work-dir" => work_dir,
     });
+    if let Some(patterns) = matches.value_of("exclude") {
+        tarballer.exclude(split_list(patterns));
+    }
+    if matches.is_present("reproducible") {
+        tarballer.reproducible(true);
+    }
 
     tarballer.run().chain_err(|| "failed to generate tarballs")
 }
+
+/// Split a comma-separated argument into its non-empty items.
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',').filter(|s| !s.is_empty()).map(String::from).collect()
+}
@@ -15,7 +15,7 @@ use std::path::Path;
 use flate2;
 use flate2::write::GzEncoder;
 use rayon;
-use tar::{Builder, Header};
+use tar::{Builder, Header, HeaderMode};
 use walkdir::WalkDir;
 use xz2::write::XzEncoder;
 
@@ -33,6 +33,13 @@ actor!{
 
         /// The folder in which the input is to be found
         work_dir: String = "./workdir",
+
+        /// Glob patterns of paths in the input to leave out of the tarballs
+        exclude: Vec<String> = Vec::new(),
+
+        /// Whether to zero the timestamps and ownership of all entries and to give
+        /// them standard permissions, so that the same input gives the same tarballs
+        reproducible: bool = false,
     }
 }
 
@@ -52,10 +59,19 @@ impl Tarballer {
         // Sort files by their suffix, to group files with the same name from
         // different locations (likely identical) and files with the same
         // extension (likely containing similar data).
-        let (dirs, mut files) = get_recursive_paths(&self.work_dir, &self.input)
+        let (mut dirs, mut files) = get_recursive_paths(&self.work_dir, &self.input, &self.exclude)
             .chain_err(|| "failed to collect file paths")?;
         files.sort_by(|a, b| a.bytes().rev().cmp(b.bytes().rev()));
 
+        // The order of directories depends on the file system otherwise.
+        // Parents still come first, since they sort before their contents.
+        let mode = if self.reproducible {
+            dirs.sort();
+            HeaderMode::Deterministic
+        } else {
+            HeaderMode::Complete
+        };
+
         // Prepare the .tar.gz file
         let gz = GzEncoder::new(create_new_file(tar_gz)?, flate2::Compression::best());
 
@@ -67,6 +83,7 @@ impl Tarballer {
         let tee = RayonTee(xz, gz);
         let buf = BufWriter::with_capacity(1024 * 1024, tee);
         let mut builder = Builder::new(buf);
+        builder.mode(mode);
 
         let pool = rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap();
         pool.install(move || {
@@ -77,7 +94,7 @@ impl Tarballer {
             }
             for path in files {
                 let src = Path::new(&self.work_dir).join(&path);
-                append_path(&mut builder, &src, &path)
+                append_path(&mut builder, &src, &path, mode)
                     .chain_err(|| format!("failed to tar file '{}'", src.display()))?;
             }
             let RayonTee(xz, gz) = builder.into_inner()
@@ -94,10 +111,12 @@ impl Tarballer {
     }
 }
 
-fn append_path<W: Write>(builder: &mut Builder<W>, src: &Path, path: &String) -> Result<()> {
+fn append_path<W: Write>(builder: &mut Builder<W>, src: &Path, path: &String, mode: HeaderMode)
+    -> Result<()>
+{
     let stat = symlink_metadata(src)?;
     let mut header = Header::new_gnu();
-    header.set_metadata(&stat);
+    header.set_metadata_in_mode(&stat, mode);
     if stat.file_type().is_symlink() {
         let link = read_link(src)?;
         header.set_link_name(&link)?;
@@ -120,8 +139,10 @@ fn append_path<W: Write>(builder: &mut Builder<W>, src: &Path, path: &String) ->
     Ok(())
 }
 
-/// Returns all `(directories, files)` under the source path
-fn get_recursive_paths<P, Q>(root: P, name: Q) -> Result<(Vec<String>, Vec<String>)>
+/// Returns all `(directories, files)` under the source path, except for those
+/// matching the `exclude` patterns
+fn get_recursive_paths<P, Q>(root: P, name: Q, exclude: &[String])
+    -> Result<(Vec<String>, Vec<String>)>
     where P: AsRef<Path>, Q: AsRef<Path>
 {
     let root = root.as_ref();
@@ -133,7 +154,11 @@ fn get_recursive_paths<P, Q>(root: P, name: Q) -> Result<(Vec<String>, Vec<Strin
 
     let mut dirs = vec![];
     let mut files = vec![];
-    for entry in WalkDir::new(root.join(name)) {
+    let input = root.join(name);
+    let walker = WalkDir::new(&input).into_iter().filter_entry(|entry| {
+        entry.path().strip_prefix(&input).map_or(true, |path| !is_excluded(path, exclude))
+    });
+    for entry in walker {
         let entry = entry?;
         let path = entry.path().strip_prefix(root)?;
         let path = path_to_str(&path)?;
@@ -93,15 +93,20 @@ pub fn remove_file<P: AsRef<Path>>(path: P) -> Result<()> {
 /// Copies the `src` directory recursively to `dst`. Both are assumed to exist
 /// when this function is called.
 pub fn copy_recursive(src: &Path, dst: &Path) -> Result<()> {
-    copy_with_callback(src, dst, |_, _| Ok(()))
+    copy_with_callback(src, dst, &[], |_, _| Ok(()))
 }
 
 /// Copies the `src` directory recursively to `dst`. Both are assumed to exist
 /// when this function is called.  Invokes a callback for each path visited.
-pub fn copy_with_callback<F>(src: &Path, dst: &Path, mut callback: F) -> Result<()>
+/// Paths matching one of the `exclude` patterns are skipped (see `is_excluded`).
+pub fn copy_with_callback<F>(src: &Path, dst: &Path, exclude: &[String], mut callback: F)
+    -> Result<()>
     where F: FnMut(&Path, fs::FileType) -> Result<()>
 {
-    for entry in WalkDir::new(src).min_depth(1) {
+    let walker = WalkDir::new(src).min_depth(1).into_iter().filter_entry(|entry| {
+        entry.path().strip_prefix(src).map_or(true, |path| !is_excluded(path, exclude))
+    });
+    for entry in walker {
         let entry = entry?;
         let file_type = entry.file_type();
         let path = entry.path().strip_prefix(src)?;
@@ -117,6 +122,51 @@ pub fn copy_with_callback<F>(src: &Path, dst: &Path, mut callback: F) -> Result<
     Ok(())
 }
 
+/// Returns `true` if `path` matches one of the glob `patterns`.
+///
+/// Patterns containing a `/` are matched against the whole relative `path`, the others
+/// against its file name only. `*` matches any run of characters and `?` any single
+/// character, except for `/`.
+pub fn is_excluded(path: &Path, patterns: &[String]) -> bool {
+    if patterns.is_empty() {
+        return false;
+    }
+    let path = path.to_string_lossy().replace('\\', "/");
+    let name = path.rsplit('/').next().unwrap_or("");
+    patterns.iter().any(|pattern| {
+        if pattern.contains('/') {
+            glob_matches(pattern.trim_matches('/'), &path)
+        } else {
+            glob_matches(pattern, name)
+        }
+    })
+}
+
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let mut pattern_chars = pattern.chars();
+    match pattern_chars.next() {
+        None => text.is_empty(),
+        Some('*') => {
+            let rest = pattern_chars.as_str();
+            let mut text = text;
+            loop {
+                if glob_matches(rest, text) {
+                    return true;
+                }
+                match text.chars().next() {
+                    Some(c) if c != '/' => text = &text[c.len_utf8()..],
+                    _ => return false,
+                }
+            }
+        }
+        Some(p) => match text.chars().next() {
+            Some(c) if (p == '?' && c != '/') || p == c => {
+                glob_matches(pattern_chars.as_str(), &text[c.len_utf8()..])
+            }
+            _ => false,
+        },
+    }
+}
 
 /// Create an "actor" with default values and setters for all fields.
 macro_rules! actor {
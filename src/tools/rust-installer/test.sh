@@ -286,6 +286,66 @@ tarball_with_package_name() {
 }
 runtest tarball_with_package_name
 
+tarball_with_excluded_files() {
+    try cp -R "$TEST_DIR/image1" "$WORK_DIR/image"
+    try sh "$S/make-tarballs.sh" \
+	--input=image \
+	--output="$OUT_DIR/image" \
+	--work-dir="$WORK_DIR" \
+	--exclude="*-to-not-install,bin/program?"
+    expect_output_ok "image/bin/program" tar -tzf "$OUT_DIR/image.tar.gz"
+    expect_not_output_ok "to-not-install" tar -tzf "$OUT_DIR/image.tar.gz"
+    expect_not_output_ok "program2" tar -tzf "$OUT_DIR/image.tar.gz"
+    expect_not_output_ok "program2" tar -tJf "$OUT_DIR/image.tar.xz"
+}
+runtest tarball_with_excluded_files
+
+reproducible_tarballs() {
+    try cp -R "$TEST_DIR/image1" "$WORK_DIR/image"
+    try sh "$S/make-tarballs.sh" \
+	--input=image \
+	--output="$OUT_DIR/first" \
+	--work-dir="$WORK_DIR" \
+	--reproducible
+    try sh "$S/make-tarballs.sh" \
+	--input=image \
+	--output="$OUT_DIR/first-default" \
+	--work-dir="$WORK_DIR"
+    try touch -t 200001010000 "$WORK_DIR/image/something-to-install" "$WORK_DIR/image/bin"
+    try chmod g+w "$WORK_DIR/image/bin/program"
+    try sh "$S/make-tarballs.sh" \
+	--input=image \
+	--output="$OUT_DIR/second" \
+	--work-dir="$WORK_DIR" \
+	--reproducible
+    try sh "$S/make-tarballs.sh" \
+	--input=image \
+	--output="$OUT_DIR/second-default" \
+	--work-dir="$WORK_DIR"
+    try cmp "$OUT_DIR/first.tar.gz" "$OUT_DIR/second.tar.gz"
+    try cmp "$OUT_DIR/first.tar.xz" "$OUT_DIR/second.tar.xz"
+    expect_fail cmp "$OUT_DIR/first-default.tar.gz" "$OUT_DIR/second-default.tar.gz"
+    expect_output_ok "rwxr-xr-x 0/0 .* image/bin/program$" \
+	tar -tvzf "$OUT_DIR/second.tar.gz" --numeric-owner
+}
+runtest reproducible_tarballs
+
+install_with_excluded_files() {
+    try sh "$S/gen-installer.sh" \
+	--image-dir="$TEST_DIR/image1" \
+	--work-dir="$WORK_DIR" \
+	--output-dir="$OUT_DIR" \
+	--exclude="*-to-not-install,bin/program?"
+    expect_not_output_ok "to-not-install" tar -tzf "$OUT_DIR/package.tar.gz"
+    try "$WORK_DIR/package/install.sh" --prefix="$PREFIX_DIR"
+    try test -e "$PREFIX_DIR/something-to-install"
+    try test -e "$PREFIX_DIR/bin/program"
+    try test ! -e "$PREFIX_DIR/something-to-not-install"
+    try test ! -e "$PREFIX_DIR/dir-to-not-install"
+    try test ! -e "$PREFIX_DIR/bin/program2"
+}
+runtest install_with_excluded_files
+
 install_overwrite_backup() {
     try sh "$S/gen-installer.sh" \
 	--image-dir="$TEST_DIR/image1" \